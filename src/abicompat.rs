@@ -0,0 +1,83 @@
+//! Module containing compatibility shims between the `ethabi` token types
+//! used by `web3` and the ones re-exported by `ethcontract-common`.
+//!
+//! The two crates may pin different versions of `ethabi`, so tokens need to
+//! be converted at the boundary even though the shapes are identical.
+
+use ethcontract_common::abi::Token as CommonToken;
+use web3::ethabi::Token as Web3Token;
+
+/// Converts a value from one `ethabi` token representation to the other.
+pub trait AbiCompat {
+    /// The compatible output type.
+    type Compat;
+
+    /// Performs the conversion.
+    fn compat(self) -> Self::Compat;
+}
+
+impl AbiCompat for Web3Token {
+    type Compat = CommonToken;
+
+    fn compat(self) -> Self::Compat {
+        match self {
+            Web3Token::Address(value) => CommonToken::Address(value),
+            Web3Token::FixedBytes(value) => CommonToken::FixedBytes(value),
+            Web3Token::Bytes(value) => CommonToken::Bytes(value),
+            Web3Token::Int(value) => CommonToken::Int(value),
+            Web3Token::Uint(value) => CommonToken::Uint(value),
+            Web3Token::Bool(value) => CommonToken::Bool(value),
+            Web3Token::String(value) => CommonToken::String(value),
+            Web3Token::FixedArray(value) => {
+                CommonToken::FixedArray(value.into_iter().map(AbiCompat::compat).collect())
+            }
+            Web3Token::Array(value) => {
+                CommonToken::Array(value.into_iter().map(AbiCompat::compat).collect())
+            }
+            Web3Token::Tuple(value) => {
+                CommonToken::Tuple(value.into_iter().map(AbiCompat::compat).collect())
+            }
+        }
+    }
+}
+
+impl AbiCompat for Vec<Web3Token> {
+    type Compat = Vec<CommonToken>;
+
+    fn compat(self) -> Self::Compat {
+        self.into_iter().map(AbiCompat::compat).collect()
+    }
+}
+
+impl AbiCompat for CommonToken {
+    type Compat = Web3Token;
+
+    fn compat(self) -> Self::Compat {
+        match self {
+            CommonToken::Address(value) => Web3Token::Address(value),
+            CommonToken::FixedBytes(value) => Web3Token::FixedBytes(value),
+            CommonToken::Bytes(value) => Web3Token::Bytes(value),
+            CommonToken::Int(value) => Web3Token::Int(value),
+            CommonToken::Uint(value) => Web3Token::Uint(value),
+            CommonToken::Bool(value) => Web3Token::Bool(value),
+            CommonToken::String(value) => Web3Token::String(value),
+            CommonToken::FixedArray(value) => {
+                Web3Token::FixedArray(value.into_iter().map(AbiCompat::compat).collect())
+            }
+            CommonToken::Array(value) => {
+                Web3Token::Array(value.into_iter().map(AbiCompat::compat).collect())
+            }
+            CommonToken::Tuple(value) => {
+                Web3Token::Tuple(value.into_iter().map(AbiCompat::compat).collect())
+            }
+        }
+    }
+}
+
+impl AbiCompat for Vec<CommonToken> {
+    type Compat = Vec<Web3Token>;
+
+    fn compat(self) -> Self::Compat {
+        self.into_iter().map(AbiCompat::compat).collect()
+    }
+}
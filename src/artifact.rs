@@ -0,0 +1,136 @@
+//! Module for constructing `Artifact`s from build systems other than
+//! Truffle, which `ethcontract_common::Artifact` (with its
+//! `networks: HashMap<String, Network>` deployment map) is shaped around.
+
+use ethcontract_common::{Abi, Artifact, Bytecode};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A loader that constructs an [`Artifact`] from some build-tool-specific
+/// representation, mapping its fields onto the `abi`/`bytecode`/`networks`
+/// that [`Deployments`](crate::contract::Deployments) and
+/// [`Linker`](crate::contract::Linker) consume. This lets callers point the
+/// crate at build output other than a Truffle artifact.
+pub trait ArtifactLoader {
+    /// The error produced when this loader fails to produce an artifact.
+    type Error;
+
+    /// Loads the artifact.
+    fn load(self) -> Result<Artifact, Self::Error>;
+}
+
+/// Loads an `Artifact` from Hardhat's `hh-sol-artifact-1` JSON format (see
+/// <https://hardhat.org/hardhat-runner/docs/advanced/artifacts>), which
+/// lacks Truffle's per-network deployment addresses.
+#[derive(Debug, Clone)]
+pub struct HardhatArtifact(String);
+
+impl HardhatArtifact {
+    /// Creates a new loader from the raw contents of a Hardhat artifact
+    /// JSON file.
+    pub fn new(json: impl Into<String>) -> Self {
+        HardhatArtifact(json.into())
+    }
+}
+
+impl ArtifactLoader for HardhatArtifact {
+    type Error = serde_json::Error;
+
+    fn load(self) -> Result<Artifact, Self::Error> {
+        #[derive(Deserialize)]
+        struct HhSolArtifact {
+            abi: Abi,
+            bytecode: Bytecode,
+        }
+
+        let artifact: HhSolArtifact = serde_json::from_str(&self.0)?;
+        Ok(Artifact {
+            abi: artifact.abi,
+            bytecode: artifact.bytecode,
+            networks: HashMap::new(),
+        })
+    }
+}
+
+/// Loads an `Artifact` from a standalone ABI and optional bytecode, with no
+/// associated network deployments. Useful when only interacting with an
+/// already-deployed contract, where no deployment bytecode is needed.
+#[derive(Debug, Clone)]
+pub struct AbiArtifact {
+    abi: Abi,
+    bytecode: Bytecode,
+}
+
+impl AbiArtifact {
+    /// Creates a new loader from an already-parsed ABI and optional
+    /// deployment bytecode.
+    pub fn new(abi: Abi, bytecode: Option<Vec<u8>>) -> Self {
+        AbiArtifact {
+            abi,
+            bytecode: Bytecode::from(bytecode.unwrap_or_default()),
+        }
+    }
+}
+
+impl ArtifactLoader for AbiArtifact {
+    type Error = std::convert::Infallible;
+
+    fn load(self) -> Result<Artifact, Self::Error> {
+        Ok(Artifact {
+            abi: self.abi,
+            bytecode: self.bytecode,
+            networks: HashMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABI_JSON: &str = r#"[{
+        "type": "function",
+        "name": "transfer",
+        "inputs": [
+            { "name": "to", "type": "address" },
+            { "name": "amount", "type": "uint256" }
+        ],
+        "outputs": [{ "name": "", "type": "bool" }]
+    }]"#;
+
+    fn test_abi() -> Abi {
+        serde_json::from_str(ABI_JSON).expect("valid ABI JSON")
+    }
+
+    #[test]
+    fn hardhat_artifact_parses_abi_and_bytecode() {
+        let json = format!(r#"{{"abi": {}, "bytecode": "0x1234"}}"#, ABI_JSON);
+
+        let artifact = HardhatArtifact::new(json).load().unwrap();
+
+        assert!(artifact.abi.functions.contains_key("transfer"));
+        assert_eq!(artifact.bytecode.to_bytes().unwrap(), vec![0x12, 0x34]);
+        assert!(artifact.networks.is_empty());
+    }
+
+    #[test]
+    fn hardhat_artifact_rejects_malformed_json() {
+        let result = HardhatArtifact::new("not json").load();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn abi_artifact_defaults_to_empty_bytecode() {
+        let artifact = AbiArtifact::new(test_abi(), None).load().unwrap();
+
+        assert_eq!(artifact.bytecode.to_bytes().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn abi_artifact_uses_the_given_bytecode() {
+        let artifact = AbiArtifact::new(test_abi(), Some(vec![0xde, 0xad])).load().unwrap();
+
+        assert_eq!(artifact.bytecode.to_bytes().unwrap(), vec![0xde, 0xad]);
+    }
+}
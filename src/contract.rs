@@ -4,27 +4,36 @@
 
 mod deploy;
 mod deployed;
+mod deployment;
 mod event;
 mod method;
 
 use crate::abicompat::AbiCompat;
-use crate::errors::{DeployError, LinkError};
+use crate::artifact::ArtifactLoader;
+use crate::ens::NameOrAddress;
+use crate::errors::{DeployError, ExecutionError, LinkError};
 use crate::log::LogStream;
-use ethcontract_common::abi::{Error as AbiError, Result as AbiResult};
+use ethcontract_common::abi::Result as AbiResult;
 use ethcontract_common::abiext::FunctionExt;
 use ethcontract_common::truffle::Network;
 use ethcontract_common::{Abi, Artifact, Bytecode};
+use futures::compat::Future01CompatExt;
 use std::collections::HashMap;
 use web3::api::Web3;
 use web3::contract::tokens::{Detokenize, Tokenize};
-use web3::types::{Address, Bytes, FilterBuilder};
+use web3::types::{Address, BlockNumber, Bytes, FilterBuilder};
 use web3::Transport;
 
 pub use self::deploy::{Deploy, DeployBuilder, DeployFuture};
 pub use self::deployed::{DeployedFuture, FromNetwork};
-pub use self::event::{Event, EventBuilder, EventStream, Topic, DEFAULT_POLL_INTERVAL};
+pub use self::deployment::DeploymentInformation;
+pub use self::event::{
+    AllEventsBuilder, AllEventsStream, Event, EventBuilder, EventMetadata, EventStream, ParseLog,
+    StreamEvent, Topic, DEFAULT_POLL_INTERVAL,
+};
 pub use self::method::{
-    CallFuture, MethodBuilder, MethodDefaults, MethodFuture, MethodSendFuture, ViewMethodBuilder,
+    CallFuture, GasPrice, MethodBuilder, MethodDefaults, MethodFuture, MethodSendFuture,
+    MethodSignature, Signature, ViewMethodBuilder, H32,
 };
 
 /// Represents a contract instance at an address. Provides methods for
@@ -41,6 +50,17 @@ pub struct Instance<T: Transport> {
     /// functions in the contract ABI. This is used to avoid allocation when
     /// searching for matching functions by signature.
     methods: HashMap<String, (String, usize)>,
+    /// The same name-index pairs as `methods`, keyed by the function's
+    /// 4-byte selector instead, for looking up methods by a strongly-typed
+    /// [`Signature`].
+    methods_by_selector: HashMap<H32, (String, usize)>,
+    /// Information about where and when this contract was deployed, used as
+    /// the default lower bound for historic event queries.
+    deployment_information: Option<DeploymentInformation>,
+    /// The ENS name this instance was resolved from via
+    /// [`Instance::at_name`], cached so it doesn't need to be resolved
+    /// again to be reported back to callers.
+    ens_name: Option<String>,
 }
 
 impl<T: Transport> Instance<T> {
@@ -50,22 +70,64 @@ impl<T: Transport> Instance<T> {
     /// Note that this does not verify that a contract with a matchin `Abi` is
     /// actually deployed at the given address.
     pub fn at(web3: Web3<T>, abi: Abi, address: Address) -> Self {
-        let methods = abi
-            .functions
-            .iter()
-            .flat_map(|(name, functions)| {
-                functions.iter().enumerate().map(move |(index, function)| {
-                    (function.abi_signature(), (name.to_owned(), index))
-                })
-            })
-            .collect();
+        Instance::with_deployment_info(web3, abi, address, None)
+    }
+
+    /// Creates a new contract instance like [`Instance::at`], additionally
+    /// recording the given deployment information so that historic event
+    /// queries can default their lower bound to the contract's deployment
+    /// block instead of the genesis block.
+    pub fn with_deployment_info(
+        web3: Web3<T>,
+        abi: Abi,
+        address: Address,
+        deployment_information: Option<DeploymentInformation>,
+    ) -> Self {
+        let mut methods = HashMap::new();
+        let mut methods_by_selector = HashMap::new();
+        for (name, functions) in &abi.functions {
+            for (index, function) in functions.iter().enumerate() {
+                methods.insert(function.abi_signature(), (name.to_owned(), index));
+                methods_by_selector.insert(H32(function.short_signature()), (name.to_owned(), index));
+            }
+        }
         Instance {
             web3,
             abi,
             address,
             defaults: MethodDefaults::default(),
             methods,
+            methods_by_selector,
+            deployment_information,
+            ens_name: None,
+        }
+    }
+
+    /// Creates a new contract instance at the address an ENS `name` resolves
+    /// to, querying the canonical ENS registry over `web3`. `name` also
+    /// accepts a raw [`Address`], in which case no resolution is performed.
+    ///
+    /// Note that this does not verify that a contract with a matching `Abi`
+    /// is actually deployed at the resolved address.
+    pub async fn at_name(
+        web3: Web3<T>,
+        abi: Abi,
+        name: impl Into<NameOrAddress>,
+    ) -> Result<Self, DeployError> {
+        let name = name.into();
+        let address = name.resolve(&web3).await?;
+
+        let mut instance = Instance::at(web3, abi, address);
+        if let NameOrAddress::Name(name) = name {
+            instance.ens_name = Some(name);
         }
+        Ok(instance)
+    }
+
+    /// Returns the ENS name this instance was resolved from via
+    /// [`Instance::at_name`], if any.
+    pub fn ens_name(&self) -> Option<&str> {
+        self.ens_name.as_deref()
     }
 
     /// Locates a deployed contract based on the current network ID reported by
@@ -77,6 +139,24 @@ impl<T: Transport> Instance<T> {
         DeployedFuture::new(web3, Deployments::new(artifact))
     }
 
+    /// Like [`Instance::deployed`], but loads the artifact from an
+    /// [`ArtifactLoader`] (e.g. [`HardhatArtifact`](crate::artifact::HardhatArtifact)
+    /// or [`AbiArtifact`](crate::artifact::AbiArtifact)) instead of an
+    /// already-constructed Truffle [`Artifact`].
+    pub fn deployed_with_loader<L>(web3: Web3<T>, loader: L) -> Result<DeployedFuture<T, Self>, DeployError>
+    where
+        L: ArtifactLoader,
+        L::Error: std::error::Error + Send + Sync + 'static,
+    {
+        Ok(Instance::deployed(web3, load_artifact(loader)?))
+    }
+
+    /// Returns the deployment information recorded for this instance, if
+    /// any.
+    pub fn deployment_information(&self) -> Option<DeploymentInformation> {
+        self.deployment_information
+    }
+
     /// Creates a contract builder with the specified `web3` provider and the
     /// given `Artifact` byte code. This allows the contract deployment
     /// transaction to be configured before deploying the contract.
@@ -91,6 +171,23 @@ impl<T: Transport> Instance<T> {
         Linker::new(artifact).deploy(web3, params)
     }
 
+    /// Like [`Instance::builder`], but loads the artifact from an
+    /// [`ArtifactLoader`] (e.g. [`HardhatArtifact`](crate::artifact::HardhatArtifact)
+    /// or [`AbiArtifact`](crate::artifact::AbiArtifact)) instead of an
+    /// already-constructed Truffle [`Artifact`].
+    pub fn builder_with_loader<P, L>(
+        web3: Web3<T>,
+        loader: L,
+        params: P,
+    ) -> Result<DeployBuilder<T, Self>, DeployError>
+    where
+        P: Tokenize,
+        L: ArtifactLoader,
+        L::Error: std::error::Error + Send + Sync + 'static,
+    {
+        Linker::from_loader(loader)?.deploy(web3, params)
+    }
+
     /// Deploys a contract with the specified `web3` provider with the given
     /// `Artifact` byte code and linking libraries.
     pub fn link_and_deploy<'a, P, I>(
@@ -129,23 +226,22 @@ impl<T: Transport> Instance<T> {
     /// Returns a method builder to setup a call or transaction on a smart
     /// contract method. Note that calls just get evaluated on a node but do not
     /// actually commit anything to the block chain.
+    ///
+    /// `signature` is either the method's full Solidity signature text (e.g.
+    /// `"transfer(address,uint256)"`) or a strongly-typed [`Signature<P,
+    /// R>`], which is looked up by its 4-byte selector instead of by text.
+    /// Either form fails with `AbiError::InvalidName` if the contract ABI
+    /// has no matching function.
     pub fn method<S, P, R>(&self, signature: S, params: P) -> AbiResult<MethodBuilder<T, R>>
     where
-        S: AsRef<str>,
+        S: MethodSignature<P, R>,
         P: Tokenize,
     {
-        let signature = signature.as_ref();
-        let function = self
-            .methods
-            .get(signature)
-            .map(|(name, index)| &self.abi.functions[name][*index])
-            .ok_or_else(|| AbiError::InvalidName(signature.into()))?;
-        let data = function.encode_input(&params.into_tokens().compat())?;
-
         // take ownership here as it greatly simplifies dealing with futures
         // lifetime as it would require the contract Instance to live until
         // the end of the future
-        let function = function.clone();
+        let function = signature.lookup(&self.abi, &self.methods, &self.methods_by_selector)?;
+        let data = function.encode_input(&params.into_tokens().compat())?;
         let data = Bytes(data);
 
         Ok(
@@ -163,7 +259,7 @@ impl<T: Transport> Instance<T> {
         params: P,
     ) -> AbiResult<ViewMethodBuilder<T, R>>
     where
-        S: AsRef<str>,
+        S: MethodSignature<P, R>,
         P: Tokenize,
         R: Detokenize,
     {
@@ -172,26 +268,88 @@ impl<T: Transport> Instance<T> {
 
     /// Returns a event builder to setup an event stream for a smart contract
     /// that emits events for the specified Solidity event by name.
-    pub fn event<S, E>(&self, name: S) -> AbiResult<EventBuilder<T, E>>
+    ///
+    /// The builder's `from_block` defaults to this instance's deployment
+    /// block, if known, so that the resulting stream replays the contract's
+    /// full historic log set rather than only new events. Resolving a
+    /// deployment transaction hash into a block number costs an extra node
+    /// round trip, so this is `async`.
+    pub async fn event<S, E>(&self, name: S) -> Result<EventBuilder<T, E>, ExecutionError>
     where
         S: AsRef<str>,
         E: Detokenize,
     {
         let event = self.abi.event(name.as_ref())?;
+        let from_block = self.deployment_block_number().await?;
 
-        Ok(EventBuilder::new(
-            self.web3(),
-            event.clone(),
-            self.address(),
-        ))
+        Ok(EventBuilder::new(self.web3(), event.clone(), self.address()).from_block(from_block))
+    }
+
+    /// Returns a builder for streaming every event emitted by this contract,
+    /// decoded into `E` where possible.
+    ///
+    /// The builder's `from_block` defaults to this instance's deployment
+    /// block, if known, so that the resulting stream replays the contract's
+    /// full historic log set rather than only new events. Resolving a
+    /// deployment transaction hash into a block number costs an extra node
+    /// round trip, so this is `async`.
+    pub async fn all_events<E>(&self) -> Result<AllEventsBuilder<T, E>, ExecutionError> {
+        let from_block = self.deployment_block_number().await?;
+        Ok(AllEventsBuilder::new(self.web3(), self.abi.clone(), self.address()).from_block(from_block))
     }
 
-    /// Returns a log stream that emits a log for every new event emitted after
-    /// the stream was created for this contract instance.
-    pub fn all_events(&self) -> LogStream<T> {
-        let filter = FilterBuilder::default().address(vec![self.address]).build();
+    /// Returns a raw, undecoded log stream bounded by an explicit
+    /// `from_block`/`to_block` range, for replaying historic logs or
+    /// narrowing the live window without paying for ABI decoding.
+    pub fn all_events_from(
+        &self,
+        from_block: Option<BlockNumber>,
+        to_block: Option<BlockNumber>,
+    ) -> LogStream<T> {
+        let mut filter = FilterBuilder::default().address(vec![self.address]);
+        if let Some(from_block) = from_block {
+            filter = filter.from_block(from_block);
+        }
+        if let Some(to_block) = to_block {
+            filter = filter.to_block(to_block);
+        }
         LogStream::new(self.web3(), filter, DEFAULT_POLL_INTERVAL)
     }
+
+    /// Resolves this instance's deployment information into a `BlockNumber`
+    /// usable as a filter's lower bound, looking up the deployment
+    /// transaction's receipt if only its hash is known.
+    async fn deployment_block_number(&self) -> Result<Option<BlockNumber>, ExecutionError> {
+        match self.deployment_information {
+            Some(DeploymentInformation::BlockNumber(block)) => {
+                Ok(Some(BlockNumber::Number(block.into())))
+            }
+            Some(DeploymentInformation::TransactionHash(transaction_hash)) => {
+                let receipt = self
+                    .web3
+                    .eth()
+                    .transaction_receipt(transaction_hash)
+                    .compat()
+                    .await?;
+                Ok(receipt
+                    .and_then(|receipt| receipt.block_number)
+                    .map(BlockNumber::Number))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Loads an artifact from an [`ArtifactLoader`], boxing its error into a
+/// [`DeployError::Artifact`] so `Linker::from_loader`,
+/// `Instance::deployed_with_loader` and `Instance::builder_with_loader` can
+/// report it the same way as any other deployment failure.
+fn load_artifact<L>(loader: L) -> Result<Artifact, DeployError>
+where
+    L: ArtifactLoader,
+    L::Error: std::error::Error + Send + Sync + 'static,
+{
+    loader.load().map_err(|err| DeployError::Artifact(Box::new(err)))
 }
 
 /// Deployment information for for an `Instance`. This includes the contract ABI
@@ -217,8 +375,14 @@ impl<T: Transport> FromNetwork<T> for Instance<T> {
     type Context = Deployments;
 
     fn from_network(web3: Web3<T>, network_id: &str, cx: Self::Context) -> Option<Self> {
-        let address = cx.networks.get(network_id)?.address;
-        Some(Instance::at(web3, cx.abi, address))
+        let network = cx.networks.get(network_id)?;
+        let deployment_information = network.transaction_hash.map(DeploymentInformation::from);
+        Some(Instance::with_deployment_info(
+            web3,
+            cx.abi,
+            network.address,
+            deployment_information,
+        ))
     }
 }
 
@@ -240,6 +404,18 @@ impl Linker {
         }
     }
 
+    /// Like [`Linker::new`], but loads the artifact from an
+    /// [`ArtifactLoader`] (e.g. [`HardhatArtifact`](crate::artifact::HardhatArtifact)
+    /// or [`AbiArtifact`](crate::artifact::AbiArtifact)) instead of an
+    /// already-constructed Truffle [`Artifact`].
+    pub fn from_loader<L>(loader: L) -> Result<Linker, DeployError>
+    where
+        L: ArtifactLoader,
+        L::Error: std::error::Error + Send + Sync + 'static,
+    {
+        Ok(Linker::new(load_artifact(loader)?))
+    }
+
     /// Specify a linked library used for this contract. Note that we
     /// incrementally link so that we can verify each time a library is linked
     /// whether it was successful or not.
@@ -282,7 +458,12 @@ impl<T: Transport> Deploy<T> for Instance<T> {
         &cx.bytecode
     }
 
-    fn at_address(web3: Web3<T>, address: Address, cx: Self::Context) -> Self {
-        Instance::at(web3, cx.abi, address)
+    fn at_address(
+        web3: Web3<T>,
+        address: Address,
+        cx: Self::Context,
+        deployment_information: Option<DeploymentInformation>,
+    ) -> Self {
+        Instance::with_deployment_info(web3, cx.abi, address, deployment_information)
     }
 }
@@ -0,0 +1,295 @@
+//! Implementation for deploying a contract, linking its bytecode and waiting
+//! for the deployment transaction to be mined.
+
+use crate::contract::deployment::DeploymentInformation;
+use crate::contract::event::DEFAULT_POLL_INTERVAL;
+use crate::contract::method::GasPrice;
+use crate::errors::DeployError;
+use ethcontract_common::{Abi, Bytecode};
+use futures::compat::Future01CompatExt;
+use futures::future::{BoxFuture, FutureExt};
+use std::marker::PhantomData;
+use std::time::Duration;
+use web3::api::Web3;
+use web3::contract::tokens::Tokenize;
+use web3::types::{
+    Address, BlockId, BlockNumber, Bytes, TransactionId, TransactionReceipt, TransactionRequest, H256, U256, U64,
+};
+use web3::Transport;
+
+use crate::abicompat::AbiCompat;
+
+/// A trait implemented by types that can be constructed from a freshly
+/// deployed contract address, used to parameterize [`DeployBuilder`] and
+/// [`DeployFuture`] over the concrete contract instance type.
+pub trait Deploy<T: Transport>: Sized {
+    /// Context required to locate the ABI and bytecode to deploy.
+    type Context;
+
+    /// Retrieves the contract ABI from the deployment context.
+    fn abi(cx: &Self::Context) -> &Abi;
+
+    /// Retrieves the contract bytecode from the deployment context.
+    fn bytecode(cx: &Self::Context) -> &Bytecode;
+
+    /// Creates an instance of `Self` once the contract has been deployed at
+    /// the given address.
+    fn at_address(
+        web3: Web3<T>,
+        address: Address,
+        cx: Self::Context,
+        deployment_information: Option<DeploymentInformation>,
+    ) -> Self;
+}
+
+/// A builder for setting up a contract deployment transaction before it is
+/// sent, mirroring the options available on [`MethodBuilder`](crate::contract::MethodBuilder).
+#[must_use = "deployments do nothing unless you `.deploy()` them"]
+pub struct DeployBuilder<T: Transport, I: Deploy<T>> {
+    web3: Web3<T>,
+    cx: I::Context,
+    data: Bytes,
+    /// The sender address to use for the deployment transaction.
+    pub from: Option<Address>,
+    /// The amount of gas to use for the deployment transaction.
+    pub gas: Option<U256>,
+    /// The gas price configuration (legacy or EIP-1559) to use for the
+    /// deployment transaction.
+    pub gas_price: Option<GasPrice>,
+    /// The ETH value to send along with the deployment transaction.
+    pub value: Option<U256>,
+    /// The interval to wait between polls for the deployment transaction's
+    /// receipt.
+    poll_interval: Duration,
+    /// The number of blocks the deployment transaction's receipt must be
+    /// buried under before `deploy()` resolves, re-checking on each poll
+    /// that the receipt's block is still part of the canonical chain.
+    confirmations: u64,
+}
+
+impl<T: Transport, I: Deploy<T>> DeployBuilder<T, I> {
+    /// Creates a new deployment builder encoding the contract's bytecode
+    /// together with its constructor parameters.
+    pub fn new<P>(web3: Web3<T>, cx: I::Context, params: P) -> Result<Self, DeployError>
+    where
+        P: Tokenize,
+    {
+        let code = I::bytecode(&cx).to_bytes()?;
+        let data = match &I::abi(&cx).constructor {
+            Some(constructor) => constructor
+                .encode_input(code, &params.into_tokens().compat())
+                .map_err(DeployError::Abi)?,
+            None => code,
+        };
+
+        Ok(DeployBuilder {
+            web3,
+            cx,
+            data: Bytes(data),
+            from: None,
+            gas: None,
+            gas_price: None,
+            value: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            confirmations: 0,
+        })
+    }
+
+    /// Sets the sender address for the deployment transaction.
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Sets the amount of gas to use for the deployment transaction.
+    pub fn gas(mut self, gas: U256) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    /// Configures the deployment transaction to use a legacy `gasPrice` of
+    /// the given value instead of an EIP-1559 typed transaction.
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = Some(GasPrice::Legacy(Some(gas_price)));
+        self
+    }
+
+    /// Sets the `maxFeePerGas` and `maxPriorityFeePerGas` for an EIP-1559
+    /// typed deployment transaction.
+    pub fn eip1559_gas_price(mut self, max_fee_per_gas: U256, max_priority_fee_per_gas: U256) -> Self {
+        self.gas_price = Some(GasPrice::Eip1559 {
+            max_fee_per_gas: Some(max_fee_per_gas),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+        });
+        self
+    }
+
+    /// Downgrades the current gas price configuration to a legacy
+    /// transaction.
+    pub fn legacy(mut self) -> Self {
+        self.gas_price = Some(self.gas_price.unwrap_or_default().legacy());
+        self
+    }
+
+    /// Sets the ETH value to send along with the deployment transaction.
+    pub fn value(mut self, value: U256) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Sets the interval to wait between polls for the deployment
+    /// transaction's receipt.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the number of blocks the deployment transaction's receipt must
+    /// be buried under before `deploy()` resolves into an `Instance`,
+    /// protecting against returning an instance whose code later vanishes
+    /// in a chain reorganization.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Signs and sends the deployment transaction, returning a future that
+    /// resolves once the contract has been deployed.
+    pub fn deploy(self) -> DeployFuture<T, I> {
+        let gas_price = self.gas_price.unwrap_or_default();
+        let request = TransactionRequest {
+            from: self.from.unwrap_or_default(),
+            to: None,
+            gas: self.gas,
+            gas_price: match &gas_price {
+                GasPrice::Legacy(price) => *price,
+                GasPrice::Eip1559 { .. } => None,
+            },
+            value: self.value,
+            data: Some(self.data),
+            nonce: None,
+            condition: None,
+            transaction_type: gas_price.transaction_type(),
+            access_list: None,
+            max_fee_per_gas: match &gas_price {
+                GasPrice::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+                GasPrice::Legacy(_) => None,
+            },
+            max_priority_fee_per_gas: match gas_price {
+                GasPrice::Eip1559 {
+                    max_priority_fee_per_gas,
+                    ..
+                } => max_priority_fee_per_gas,
+                GasPrice::Legacy(_) => None,
+            },
+        };
+
+        let web3 = self.web3;
+        let cx = self.cx;
+        let poll_interval = self.poll_interval;
+        let confirmations = self.confirmations;
+        let web3_send = web3.clone();
+        let future = async move {
+            let tx_hash = web3_send
+                .eth()
+                .send_transaction(request)
+                .compat()
+                .await
+                .map_err(DeployError::from)?;
+            let receipt = wait_for_receipt(&web3_send, tx_hash, poll_interval, confirmations).await?;
+            let address = receipt
+                .contract_address
+                .ok_or(DeployError::Reverted(Some(tx_hash)))?;
+            let deployment_information = receipt
+                .block_number
+                .map(|block| DeploymentInformation::BlockNumber(block.as_u64()));
+            Ok(I::at_address(web3_send, address, cx, deployment_information))
+        };
+
+        DeployFuture {
+            inner: future.boxed(),
+            _web3: PhantomData,
+        }
+    }
+}
+
+/// Polls for the deployment transaction's receipt until it is mined and, if
+/// `confirmations` is non-zero, buried under that many further blocks,
+/// re-checking on each poll that the receipt's block is still part of the
+/// canonical chain.
+async fn wait_for_receipt<T: Transport>(
+    web3: &Web3<T>,
+    tx_hash: H256,
+    poll_interval: Duration,
+    confirmations: u64,
+) -> Result<TransactionReceipt, DeployError> {
+    loop {
+        let receipt = web3
+            .eth()
+            .transaction_receipt(tx_hash)
+            .compat()
+            .await
+            .map_err(DeployError::from)?;
+
+        match receipt {
+            Some(receipt) if is_confirmed(web3, &receipt, confirmations).await? => return Ok(receipt),
+            Some(_) => {}
+            None => {
+                let pending = web3
+                    .eth()
+                    .transaction(TransactionId::Hash(tx_hash))
+                    .compat()
+                    .await
+                    .map_err(DeployError::from)?;
+                if pending.is_none() {
+                    return Err(DeployError::Dropped(tx_hash));
+                }
+            }
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Returns `true` once `receipt` is buried under `confirmations` further
+/// blocks and its block is still part of the canonical chain. A receipt
+/// whose block has since been reorg'd out is treated as unconfirmed so the
+/// caller keeps polling for a new one.
+async fn is_confirmed<T: Transport>(
+    web3: &Web3<T>,
+    receipt: &TransactionReceipt,
+    confirmations: u64,
+) -> Result<bool, DeployError> {
+    let block_number = match receipt.block_number {
+        Some(block_number) => block_number,
+        None => return Ok(false),
+    };
+
+    let latest_block = web3.eth().block_number().compat().await.map_err(DeployError::from)?;
+    if latest_block < block_number + U64::from(confirmations) {
+        return Ok(false);
+    }
+
+    let canonical_hash = web3
+        .eth()
+        .block(BlockId::Number(BlockNumber::Number(block_number)))
+        .compat()
+        .await
+        .map_err(DeployError::from)?
+        .and_then(|block| block.hash);
+    Ok(canonical_hash.is_some() && canonical_hash == receipt.block_hash)
+}
+
+/// Future that resolves into a contract instance once its deployment
+/// transaction has been mined.
+pub struct DeployFuture<T: Transport, I: Deploy<T>> {
+    inner: BoxFuture<'static, Result<I, DeployError>>,
+    _web3: PhantomData<T>,
+}
+
+impl<T: Transport, I: Deploy<T>> DeployFuture<T, I> {
+    /// Drives the deployment to completion.
+    pub async fn execute(self) -> Result<I, DeployError> {
+        self.inner.await
+    }
+}
@@ -0,0 +1,56 @@
+//! Implementation for locating a contract instance from the network ID
+//! reported by a node, using the deployment addresses recorded in a
+//! Truffle-style artifact.
+
+use futures::compat::Future01CompatExt;
+use futures::future::{BoxFuture, FutureExt};
+use std::marker::PhantomData;
+use web3::api::Web3;
+use web3::Transport;
+
+use crate::errors::DeployError;
+
+/// A trait implemented by types that can be located on a particular network
+/// given some deployment context (for example, the contract ABI and a map
+/// of network IDs to addresses).
+pub trait FromNetwork<T: Transport>: Sized {
+    /// Context required to locate the contract on the network.
+    type Context;
+
+    /// Attempts to create an instance of `Self` for the given network ID,
+    /// returning `None` if no deployment is known for that network.
+    fn from_network(web3: Web3<T>, network_id: &str, cx: Self::Context) -> Option<Self>;
+}
+
+/// Future that resolves once the current network ID has been retrieved from
+/// the node and used to locate a deployed contract instance.
+pub struct DeployedFuture<T: Transport, I: FromNetwork<T>> {
+    inner: BoxFuture<'static, Result<I, DeployError>>,
+    _web3: PhantomData<T>,
+}
+
+impl<T: Transport, I: FromNetwork<T> + 'static> DeployedFuture<T, I> {
+    /// Creates a new future that locates a contract instance on the node's
+    /// current network.
+    pub fn new(web3: Web3<T>, cx: I::Context) -> Self
+    where
+        I::Context: Send + 'static,
+    {
+        let web3_net = web3.clone();
+        let future = async move {
+            let network_id = web3_net.net().version().compat().await?;
+            I::from_network(web3_net, &network_id, cx)
+                .ok_or(DeployError::NotFound(network_id))
+        };
+
+        DeployedFuture {
+            inner: future.boxed(),
+            _web3: PhantomData,
+        }
+    }
+
+    /// Drives the lookup to completion.
+    pub async fn execute(self) -> Result<I, DeployError> {
+        self.inner.await
+    }
+}
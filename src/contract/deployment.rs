@@ -0,0 +1,29 @@
+//! Types describing where and when a contract was deployed, so that event
+//! queries can be bounded to the contract's actual on-chain lifetime instead
+//! of always starting from the genesis block.
+
+use web3::types::H256;
+
+/// Information about a contract's deployment, used as the default lower
+/// bound for historic event queries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeploymentInformation {
+    /// The hash of the transaction that deployed the contract. Resolving
+    /// this to a block number requires an extra node query each time it is
+    /// needed; the resolved block number is not cached.
+    TransactionHash(H256),
+    /// The block number the contract was deployed in.
+    BlockNumber(u64),
+}
+
+impl From<H256> for DeploymentInformation {
+    fn from(transaction_hash: H256) -> Self {
+        DeploymentInformation::TransactionHash(transaction_hash)
+    }
+}
+
+impl From<u64> for DeploymentInformation {
+    fn from(block_number: u64) -> Self {
+        DeploymentInformation::BlockNumber(block_number)
+    }
+}
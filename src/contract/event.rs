@@ -0,0 +1,426 @@
+//! Implementation for setting up and streaming a single, strongly-typed
+//! contract event.
+
+use ethcontract_common::abi::{Event as AbiEvent, RawLog};
+use ethcontract_common::Abi;
+use std::time::Duration;
+use web3::api::Web3;
+use web3::contract::tokens::Detokenize;
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, H256, U256, U64};
+use web3::Transport;
+
+use crate::abicompat::AbiCompat;
+use crate::errors::ExecutionError;
+use crate::log::{EventStatus, LogStream};
+
+/// The default interval to wait between polling a node for new event logs.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(7_000);
+
+/// A filter value for an indexed event topic: either match any value, or
+/// match one of a specific set of values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Topic<V> {
+    /// Match any value for this topic.
+    Any,
+    /// Only match one of the given values for this topic.
+    OneOf(Vec<V>),
+}
+
+impl<V> Default for Topic<V> {
+    fn default() -> Self {
+        Topic::Any
+    }
+}
+
+impl<V> Topic<V> {
+    /// Converts this topic filter into the `Option<Vec<V>>` shape expected
+    /// by [`FilterBuilder::topics`].
+    fn into_filter(self) -> Option<Vec<V>> {
+        match self {
+            Topic::Any => None,
+            Topic::OneOf(values) => Some(values),
+        }
+    }
+}
+
+/// A decoded event log. Only the decoded data is kept; the log's on-chain
+/// location (block, transaction, log index) is not.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event<T> {
+    /// The decoded event data.
+    pub data: T,
+}
+
+/// A builder for setting up a filter for a single, strongly-typed contract
+/// event before streaming it.
+#[derive(Debug)]
+#[must_use = "event builders do nothing unless you `.stream()` them"]
+pub struct EventBuilder<T: Transport, E> {
+    web3: Web3<T>,
+    event: AbiEvent,
+    address: Address,
+    poll_interval: Duration,
+    /// The first block to include in the event query, defaulting to the
+    /// contract's deployment block when the builder is created through
+    /// [`Instance::event`](crate::contract::Instance::event).
+    from_block: Option<BlockNumber>,
+    /// The last block to include in the event query. `None` keeps streaming
+    /// indefinitely.
+    to_block: Option<BlockNumber>,
+    /// The number of blocks a log must be buried under before the stream
+    /// stops tracking it for a possible reorg removal, overriding
+    /// [`DEFAULT_CONFIRMATIONS`](crate::log::DEFAULT_CONFIRMATIONS).
+    confirmations: Option<u64>,
+    /// Restricts the event's 2nd indexed topic.
+    topic1: Topic<H256>,
+    /// Restricts the event's 3rd indexed topic.
+    topic2: Topic<H256>,
+    /// Restricts the event's 4th indexed topic.
+    topic3: Topic<H256>,
+    _event: std::marker::PhantomData<E>,
+}
+
+impl<T: Transport, E> EventBuilder<T, E> {
+    /// Creates a new event builder for the given contract event.
+    pub fn new(web3: Web3<T>, event: AbiEvent, address: Address) -> Self {
+        EventBuilder {
+            web3,
+            event,
+            address,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            from_block: None,
+            to_block: None,
+            confirmations: None,
+            topic1: Topic::Any,
+            topic2: Topic::Any,
+            topic3: Topic::Any,
+            _event: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the poll interval used to query the node for new event logs.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the first block to include in the event query, overriding the
+    /// contract's deployment block default.
+    pub fn from_block(mut self, from_block: impl Into<Option<BlockNumber>>) -> Self {
+        self.from_block = from_block.into();
+        self
+    }
+
+    /// Sets the last block to include in the event query.
+    pub fn to_block(mut self, to_block: impl Into<Option<BlockNumber>>) -> Self {
+        self.to_block = to_block.into();
+        self
+    }
+
+    /// Sets the number of blocks a log must be buried under before the
+    /// resulting stream stops tracking it for a possible reorg removal.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = Some(confirmations);
+        self
+    }
+
+    /// Restricts the event's 2nd indexed topic to one of the given values,
+    /// matching any value by default.
+    pub fn topic1(mut self, topic: Topic<H256>) -> Self {
+        self.topic1 = topic;
+        self
+    }
+
+    /// Restricts the event's 3rd indexed topic to one of the given values,
+    /// matching any value by default.
+    pub fn topic2(mut self, topic: Topic<H256>) -> Self {
+        self.topic2 = topic;
+        self
+    }
+
+    /// Restricts the event's 4th indexed topic to one of the given values,
+    /// matching any value by default.
+    pub fn topic3(mut self, topic: Topic<H256>) -> Self {
+        self.topic3 = topic;
+        self
+    }
+}
+
+impl<T: Transport, E: Detokenize> EventBuilder<T, E> {
+    /// Builds the filter and returns a stream that decodes and yields each
+    /// matching event log as it is observed.
+    pub fn stream(self) -> EventStream<T, E> {
+        let mut filter = FilterBuilder::default().address(vec![self.address]).topics(
+            Some(vec![self.event.signature()]),
+            self.topic1.into_filter(),
+            self.topic2.into_filter(),
+            self.topic3.into_filter(),
+        );
+        if let Some(from_block) = self.from_block {
+            filter = filter.from_block(from_block);
+        }
+        if let Some(to_block) = self.to_block {
+            filter = filter.to_block(to_block);
+        }
+
+        let mut logs = LogStream::new(self.web3, filter, self.poll_interval);
+        if let Some(confirmations) = self.confirmations {
+            logs = logs.confirmations(confirmations);
+        }
+
+        EventStream {
+            logs,
+            event: self.event,
+            _data: std::marker::PhantomData,
+        }
+    }
+}
+
+/// A stream of decoded events for a single contract event signature.
+pub struct EventStream<T: Transport, E> {
+    logs: LogStream<T>,
+    event: AbiEvent,
+    _data: std::marker::PhantomData<E>,
+}
+
+impl<T: Transport, E: Detokenize> EventStream<T, E> {
+    /// Decodes a raw log into this stream's event type.
+    fn decode(&self, log: Log) -> Result<E, ExecutionError> {
+        let raw = RawLog {
+            topics: log.topics,
+            data: log.data.0,
+        };
+        let tokens = self
+            .event
+            .parse_log(raw)?
+            .params
+            .into_iter()
+            .map(|param| param.value)
+            .collect::<Vec<_>>()
+            .compat();
+        Ok(E::from_tokens(tokens)?)
+    }
+
+    /// Polls the node once, returning every newly observed event as
+    /// [`EventStatus::Added`] and any previously observed event that a chain
+    /// reorganization has invalidated as [`EventStatus::Removed`].
+    pub async fn next_batch(&mut self) -> Result<Vec<EventStatus<E>>, ExecutionError> {
+        let batch = self.logs.next_batch().await?;
+        batch
+            .into_iter()
+            .map(|status| status.try_map(|log| self.decode(log)))
+            .collect()
+    }
+
+    /// Returns the underlying log stream used by this event stream.
+    pub fn logs(&self) -> &LogStream<T> {
+        &self.logs
+    }
+}
+
+/// On-chain location of an event log, independent of its decoded payload.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventMetadata {
+    /// Hash of the block the log was included in.
+    pub block_hash: Option<H256>,
+    /// Number of the block the log was included in.
+    pub block_number: Option<U64>,
+    /// Hash of the transaction that emitted the log.
+    pub transaction_hash: Option<H256>,
+    /// Index of the transaction that emitted the log within its block.
+    pub transaction_index: Option<U64>,
+    /// Index of the log within its block.
+    pub log_index: Option<U256>,
+}
+
+impl From<&Log> for EventMetadata {
+    fn from(log: &Log) -> Self {
+        EventMetadata {
+            block_hash: log.block_hash,
+            block_number: log.block_number,
+            transaction_hash: log.transaction_hash,
+            transaction_index: log.transaction_index,
+            log_index: log.log_index,
+        }
+    }
+}
+
+/// A single item yielded by an [`AllEventsBuilder`] stream: either a
+/// successfully decoded event together with its on-chain location, or a raw
+/// log whose first topic didn't match any event known to the decoder `E`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent<E> {
+    /// A log that was decoded into one of `E`'s variants.
+    Decoded {
+        /// The decoded event payload.
+        event: E,
+        /// Where the event was logged on-chain.
+        meta: EventMetadata,
+    },
+    /// A log whose topic0 did not match any event in the contract's ABI, or
+    /// that the decoder otherwise chose not to decode.
+    Raw {
+        /// The raw, undecoded log.
+        log: Log,
+    },
+}
+
+/// A decoder for an entire contract's set of events, implemented by the
+/// enum generated for a contract's ABI. Given the name of a Solidity event
+/// and its already ABI-decoded parameters, it builds the matching variant.
+pub trait ParseLog: Sized {
+    /// Builds the variant corresponding to `name` from its decoded
+    /// parameters, or returns `Ok(None)` if this decoder has no variant for
+    /// that event.
+    fn parse_log(name: &str, tokens: Vec<ethcontract_common::abi::Token>) -> Result<Option<Self>, ExecutionError>;
+}
+
+/// A builder for streaming every event emitted by a contract, decoded into
+/// a single strongly-typed enum `E: ParseLog`. This is the multi-event
+/// counterpart to [`EventBuilder`], which only streams a single event.
+#[must_use = "event builders do nothing unless you `.stream()` them"]
+pub struct AllEventsBuilder<T: Transport, E> {
+    web3: Web3<T>,
+    abi: Abi,
+    address: Address,
+    poll_interval: Duration,
+    from_block: Option<BlockNumber>,
+    to_block: Option<BlockNumber>,
+    confirmations: Option<u64>,
+    _event: std::marker::PhantomData<E>,
+}
+
+impl<T: Transport, E> AllEventsBuilder<T, E> {
+    /// Creates a new builder that streams every event declared in `abi`.
+    pub fn new(web3: Web3<T>, abi: Abi, address: Address) -> Self {
+        AllEventsBuilder {
+            web3,
+            abi,
+            address,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            from_block: None,
+            to_block: None,
+            confirmations: None,
+            _event: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the poll interval used to query the node for new event logs.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the first block to include in the event query, overriding the
+    /// contract's deployment block default.
+    pub fn from_block(mut self, from_block: impl Into<Option<BlockNumber>>) -> Self {
+        self.from_block = from_block.into();
+        self
+    }
+
+    /// Sets the last block to include in the event query.
+    pub fn to_block(mut self, to_block: impl Into<Option<BlockNumber>>) -> Self {
+        self.to_block = to_block.into();
+        self
+    }
+
+    /// Sets the number of blocks a log must be buried under before the
+    /// resulting stream stops tracking it for a possible reorg removal.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = Some(confirmations);
+        self
+    }
+}
+
+impl<T: Transport, E: ParseLog> AllEventsBuilder<T, E> {
+    /// Builds the filter over the contract's address (matching any of its
+    /// events) and returns a stream that decodes each log as it is
+    /// observed.
+    pub fn stream(self) -> AllEventsStream<T, E> {
+        let mut filter = FilterBuilder::default().address(vec![self.address]);
+        if let Some(from_block) = self.from_block {
+            filter = filter.from_block(from_block);
+        }
+        if let Some(to_block) = self.to_block {
+            filter = filter.to_block(to_block);
+        }
+
+        let mut logs = LogStream::new(self.web3, filter, self.poll_interval);
+        if let Some(confirmations) = self.confirmations {
+            logs = logs.confirmations(confirmations);
+        }
+
+        AllEventsStream {
+            logs,
+            abi: self.abi,
+            _event: std::marker::PhantomData,
+        }
+    }
+}
+
+/// A stream of every event emitted by a contract, decoded into `E` where
+/// possible.
+pub struct AllEventsStream<T: Transport, E> {
+    logs: LogStream<T>,
+    abi: Abi,
+    _event: std::marker::PhantomData<E>,
+}
+
+impl<T: Transport, E: ParseLog> AllEventsStream<T, E> {
+    /// Matches a raw log's first topic against the contract's ABI and, if
+    /// found, decodes it into a [`StreamEvent::Decoded`]; otherwise yields
+    /// it unchanged as a [`StreamEvent::Raw`].
+    fn decode(&self, log: Log) -> Result<StreamEvent<E>, ExecutionError> {
+        let topic0 = match log.topics.first() {
+            Some(topic0) => *topic0,
+            None => return Ok(StreamEvent::Raw { log }),
+        };
+
+        let matched = self
+            .abi
+            .events
+            .values()
+            .flatten()
+            .find(|event| event.signature() == topic0);
+
+        let event = match matched {
+            Some(event) => event,
+            None => return Ok(StreamEvent::Raw { log }),
+        };
+
+        let raw = RawLog {
+            topics: log.topics.clone(),
+            data: log.data.0.clone(),
+        };
+        let tokens = event
+            .parse_log(raw)?
+            .params
+            .into_iter()
+            .map(|param| param.value)
+            .collect::<Vec<_>>();
+
+        match E::parse_log(&event.name, tokens)? {
+            Some(decoded) => Ok(StreamEvent::Decoded {
+                event: decoded,
+                meta: EventMetadata::from(&log),
+            }),
+            None => Ok(StreamEvent::Raw { log }),
+        }
+    }
+
+    /// Polls the node once, returning every newly observed event as
+    /// [`EventStatus::Added`] and any previously observed event that a chain
+    /// reorganization has invalidated as [`EventStatus::Removed`].
+    pub async fn next_batch(&mut self) -> Result<Vec<EventStatus<StreamEvent<E>>>, ExecutionError> {
+        let batch = self.logs.next_batch().await?;
+        batch
+            .into_iter()
+            .map(|status| status.try_map(|log| self.decode(log)))
+            .collect()
+    }
+
+    /// Returns the underlying log stream used by this event stream.
+    pub fn logs(&self) -> &LogStream<T> {
+        &self.logs
+    }
+}
@@ -0,0 +1,561 @@
+//! Implementation for calling and sending transactions for a contract method.
+
+use crate::errors::ExecutionError;
+use ethcontract_common::abi::{Error as AbiError, Function, Result as AbiResult};
+use ethcontract_common::Abi;
+use futures::compat::Future01CompatExt;
+use futures::future::{self, BoxFuture, FutureExt};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use web3::api::Web3;
+use web3::contract::tokens::Detokenize;
+use web3::types::{Address, Bytes, CallRequest, TransactionRequest, H256, U256, U64};
+use web3::Transport;
+
+/// A contract method's 4-byte function selector: the first four bytes of
+/// the Keccak-256 hash of its canonical Solidity signature.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct H32(pub [u8; 4]);
+
+impl fmt::Debug for H32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// A contract method signature keyed by its 4-byte function selector
+/// instead of its stringly-typed Solidity signature text, statically tying
+/// the method's parameter type `P` and return type `R` to the selector used
+/// to look it up.
+///
+/// Generated contract bindings hand `Instance::method`/`view_method` one of
+/// these instead of a signature string, eliminating the runtime
+/// [`AbiError::InvalidName`] lookup failures a typo'd signature string can
+/// cause, and letting the method's parameter and return types drive
+/// inference at the call site.
+pub struct Signature<P, R> {
+    selector: H32,
+    _method: PhantomData<(P, R)>,
+}
+
+impl<P, R> Signature<P, R> {
+    /// Creates a new method signature for the given 4-byte selector.
+    pub const fn new(selector: [u8; 4]) -> Self {
+        Signature {
+            selector: H32(selector),
+            _method: PhantomData,
+        }
+    }
+}
+
+impl<P, R> Clone for Signature<P, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, R> Copy for Signature<P, R> {}
+
+impl<P, R> fmt::Debug for Signature<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signature").field(&self.selector).finish()
+    }
+}
+
+/// Resolves a method signature -- either a stringly-typed Solidity
+/// signature or a strongly-typed [`Signature<P, R>`] selector -- into the
+/// contract function it refers to.
+pub trait MethodSignature<P, R> {
+    /// Looks up the ABI function this signature refers to.
+    fn lookup(
+        &self,
+        abi: &Abi,
+        methods: &HashMap<String, (String, usize)>,
+        methods_by_selector: &HashMap<H32, (String, usize)>,
+    ) -> AbiResult<Function>;
+}
+
+// Blanket over `AsRef<str>` rather than separate `&str`/`String` impls so
+// that any stringly-typed signature -- `Cow<str>`, `Box<str>`, `Rc<str>`,
+// etc. -- works here too, the same as it did before `Signature<P, R>` was
+// introduced.
+impl<S: AsRef<str>, P, R> MethodSignature<P, R> for S {
+    fn lookup(
+        &self,
+        abi: &Abi,
+        methods: &HashMap<String, (String, usize)>,
+        _methods_by_selector: &HashMap<H32, (String, usize)>,
+    ) -> AbiResult<Function> {
+        let signature = self.as_ref();
+        methods
+            .get(signature)
+            .map(|(name, index)| abi.functions[name][*index].clone())
+            .ok_or_else(|| AbiError::InvalidName(signature.into()))
+    }
+}
+
+impl<P, R> MethodSignature<P, R> for Signature<P, R> {
+    fn lookup(
+        &self,
+        abi: &Abi,
+        _methods: &HashMap<String, (String, usize)>,
+        methods_by_selector: &HashMap<H32, (String, usize)>,
+    ) -> AbiResult<Function> {
+        methods_by_selector
+            .get(&self.selector)
+            .map(|(name, index)| abi.functions[name][*index].clone())
+            .ok_or_else(|| AbiError::InvalidName(format!("{:?}", self.selector)))
+    }
+}
+
+/// The gas price configuration to use when sending a transaction, covering
+/// both legacy and EIP-1559 typed transactions.
+///
+/// Defaults to an EIP-1559 transaction with the fees left unset, in which
+/// case they are sent unset in the transaction request. Use
+/// [`GasPrice::legacy`] to downgrade to a plain legacy transaction with a
+/// single `gasPrice` field instead.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GasPrice {
+    /// A legacy transaction using a single `gasPrice`. `None` means the
+    /// node's current gas price should be used.
+    Legacy(Option<U256>),
+    /// An EIP-1559 typed transaction. `None` fields are sent unset in the
+    /// transaction request; they are not estimated by this crate.
+    Eip1559 {
+        /// The maximum total fee per gas the sender is willing to pay.
+        max_fee_per_gas: Option<U256>,
+        /// The maximum priority fee (tip) per gas the sender is willing to
+        /// pay to the block's proposer.
+        max_priority_fee_per_gas: Option<U256>,
+    },
+}
+
+impl Default for GasPrice {
+    fn default() -> Self {
+        GasPrice::Eip1559 {
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        }
+    }
+}
+
+impl GasPrice {
+    /// Downgrades this gas price configuration into a legacy transaction,
+    /// discarding any EIP-1559 specific fields that may have been set.
+    pub fn legacy(self) -> Self {
+        match self {
+            GasPrice::Legacy(price) => GasPrice::Legacy(price),
+            GasPrice::Eip1559 { .. } => GasPrice::Legacy(None),
+        }
+    }
+
+    /// Returns `true` if this is a legacy gas price configuration.
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, GasPrice::Legacy(_))
+    }
+
+    /// Returns the EIP-2718 transaction type byte to send on the wire, so a
+    /// node can tell an EIP-1559 transaction (whose `max*_fee_per_gas`
+    /// fields would otherwise look identical to an all-`None` legacy one)
+    /// apart from a legacy transaction.
+    pub fn transaction_type(&self) -> Option<U64> {
+        match self {
+            GasPrice::Legacy(_) => None,
+            GasPrice::Eip1559 { .. } => Some(U64::from(2)),
+        }
+    }
+}
+
+/// Default parameters to use when sending transactions or performing calls
+/// for a contract's methods.
+#[derive(Clone, Debug, Default)]
+pub struct MethodDefaults {
+    /// Default sender address.
+    pub from: Option<Address>,
+    /// Default gas amount to use when not explicitly specified.
+    pub gas: Option<U256>,
+    /// Default gas price configuration (legacy or EIP-1559) to use when not
+    /// explicitly specified.
+    pub gas_price: Option<GasPrice>,
+}
+
+/// A builder for setting up calls and transactions for a contract method.
+#[derive(Clone, Debug)]
+#[must_use = "methods do nothing unless you `.call()` or `.send()` them"]
+pub struct MethodBuilder<T: Transport, R> {
+    web3: Web3<T>,
+    function: Function,
+    address: Address,
+    data: Bytes,
+    /// The sender address to use for this method call or transaction.
+    pub from: Option<Address>,
+    /// The amount of gas to use for this transaction.
+    pub gas: Option<U256>,
+    /// The gas price to use for this transaction, legacy or EIP-1559.
+    pub gas_price: Option<GasPrice>,
+    /// The ETH value to send along with this transaction.
+    pub value: Option<U256>,
+    _result: PhantomData<R>,
+}
+
+impl<T: Transport, R> MethodBuilder<T, R> {
+    /// Creates a new builder for a method call with the given encoded
+    /// parameters.
+    pub fn new(web3: Web3<T>, function: Function, address: Address, data: Bytes) -> Self {
+        MethodBuilder {
+            web3,
+            function,
+            address,
+            data,
+            from: None,
+            gas: None,
+            gas_price: None,
+            value: None,
+            _result: PhantomData,
+        }
+    }
+
+    /// Applies the contract instance's configured defaults to this builder,
+    /// without overwriting any values that have already been set.
+    pub fn with_defaults(mut self, defaults: &MethodDefaults) -> Self {
+        self.from = self.from.or(defaults.from);
+        self.gas = self.gas.or(defaults.gas);
+        self.gas_price = self.gas_price.or_else(|| defaults.gas_price.clone());
+        self
+    }
+
+    /// Sets the sender address for this method call or transaction.
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Sets the amount of gas to use for this transaction.
+    pub fn gas(mut self, gas: U256) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    /// Configures this transaction to use a legacy `gasPrice` of the given
+    /// value instead of an EIP-1559 typed transaction.
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = Some(GasPrice::Legacy(Some(gas_price)));
+        self
+    }
+
+    /// Sets the `maxFeePerGas` and `maxPriorityFeePerGas` for an EIP-1559
+    /// typed transaction.
+    pub fn eip1559_gas_price(mut self, max_fee_per_gas: U256, max_priority_fee_per_gas: U256) -> Self {
+        self.gas_price = Some(GasPrice::Eip1559 {
+            max_fee_per_gas: Some(max_fee_per_gas),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+        });
+        self
+    }
+
+    /// Downgrades the current gas price configuration to a legacy
+    /// transaction, using the node's current gas price if one has not
+    /// already been set.
+    pub fn legacy(mut self) -> Self {
+        self.gas_price = Some(self.gas_price.unwrap_or_default().legacy());
+        self
+    }
+
+    /// Sets the ETH value to send along with this transaction.
+    pub fn value(mut self, value: U256) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Converts this method builder into a [`ViewMethodBuilder`] that can
+    /// only be used to perform calls, not transactions.
+    pub fn view(self) -> ViewMethodBuilder<T, R> {
+        ViewMethodBuilder::from_method(self)
+    }
+}
+
+impl<T: Transport, R> MethodBuilder<T, R> {
+    /// Signs and sends the transaction, returning a future that resolves
+    /// once the transaction hash is available.
+    pub fn send(self) -> MethodSendFuture {
+        let gas_price = self.gas_price.unwrap_or_default();
+        let request = TransactionRequest {
+            from: self.from.unwrap_or_default(),
+            to: Some(self.address),
+            gas: self.gas,
+            gas_price: match &gas_price {
+                GasPrice::Legacy(price) => *price,
+                GasPrice::Eip1559 { .. } => None,
+            },
+            value: self.value,
+            data: Some(self.data),
+            nonce: None,
+            condition: None,
+            transaction_type: gas_price.transaction_type(),
+            access_list: None,
+            max_fee_per_gas: match &gas_price {
+                GasPrice::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+                GasPrice::Legacy(_) => None,
+            },
+            max_priority_fee_per_gas: match gas_price {
+                GasPrice::Eip1559 {
+                    max_priority_fee_per_gas,
+                    ..
+                } => max_priority_fee_per_gas,
+                GasPrice::Legacy(_) => None,
+            },
+        };
+
+        MethodSendFuture(Box::pin(
+            self.web3.eth().send_transaction(request).compat(),
+        ))
+    }
+}
+
+/// Future that resolves with the transaction hash of a sent method
+/// transaction.
+pub struct MethodSendFuture(BoxFuture<'static, Result<H256, web3::Error>>);
+
+impl MethodSendFuture {
+    /// Polls the inner future to completion, converting web3 errors into
+    /// [`ExecutionError`].
+    pub async fn execute(self) -> Result<H256, ExecutionError> {
+        self.0.await.map_err(ExecutionError::from)
+    }
+}
+
+/// A builder for performing a read-only contract call, that can't send a
+/// transaction or modify contract state.
+#[derive(Clone, Debug)]
+#[must_use = "view methods do nothing unless you `.call()` them"]
+pub struct ViewMethodBuilder<T: Transport, R> {
+    web3: Web3<T>,
+    function: Function,
+    address: Address,
+    data: Bytes,
+    /// The sender address to use to perform this call.
+    pub from: Option<Address>,
+    _result: PhantomData<R>,
+}
+
+impl<T: Transport, R> ViewMethodBuilder<T, R> {
+    fn from_method(method: MethodBuilder<T, R>) -> Self {
+        ViewMethodBuilder {
+            web3: method.web3,
+            function: method.function,
+            address: method.address,
+            data: method.data,
+            from: method.from,
+            _result: PhantomData,
+        }
+    }
+
+    /// Sets the sender address to use to perform this call.
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+}
+
+impl<T: Transport, R: Detokenize> ViewMethodBuilder<T, R> {
+    /// Performs the call without committing any results to the block chain,
+    /// returning a future that resolves with the decoded method return
+    /// value.
+    pub fn call(self) -> CallFuture<R> {
+        let request = CallRequest {
+            from: self.from,
+            to: Some(self.address),
+            gas: None,
+            gas_price: None,
+            value: None,
+            data: Some(self.data),
+            transaction_type: None,
+            access_list: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        };
+
+        let function = self.function;
+        let future = self
+            .web3
+            .eth()
+            .call(request, None)
+            .compat()
+            .map(move |result| {
+                let Bytes(bytes) = result.map_err(ExecutionError::from)?;
+                let tokens = function.decode_output(&bytes).map_err(ExecutionError::from)?;
+                R::from_tokens(tokens).map_err(ExecutionError::from)
+            });
+
+        CallFuture(Box::pin(future))
+    }
+}
+
+/// Future that resolves with the decoded return value of a contract call.
+pub struct CallFuture<R>(BoxFuture<'static, Result<R, ExecutionError>>);
+
+impl<R> CallFuture<R> {
+    /// Drives the call to completion.
+    pub async fn execute(self) -> Result<R, ExecutionError> {
+        self.0.await
+    }
+}
+
+/// Future returned while a method's parameters are still being resolved
+/// before it can be sent or called. Currently methods resolve synchronously,
+/// so this just wraps an already-ready value, but keeps the door open for
+/// asynchronous parameter resolution (e.g. nonce lookups) in the future.
+pub struct MethodFuture<R>(BoxFuture<'static, Result<R, ExecutionError>>);
+
+impl<R> MethodFuture<R> {
+    /// Creates a new, already resolved method future.
+    pub fn ready(value: R) -> Self {
+        MethodFuture(future::ready(Ok(value)).boxed())
+    }
+
+    /// Drives the future to completion.
+    pub async fn execute(self) -> Result<R, ExecutionError> {
+        self.0.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethcontract_common::abiext::FunctionExt;
+    use web3::types::Address;
+
+    const ABI_JSON: &str = r#"[{
+        "type": "function",
+        "name": "transfer",
+        "inputs": [
+            {"name": "to", "type": "address"},
+            {"name": "amount", "type": "uint256"}
+        ],
+        "outputs": [{"name": "", "type": "bool"}],
+        "stateMutability": "nonpayable"
+    }]"#;
+
+    fn test_abi() -> Abi {
+        serde_json::from_str(ABI_JSON).expect("valid ABI JSON")
+    }
+
+    fn test_maps(abi: &Abi) -> (HashMap<String, (String, usize)>, HashMap<H32, (String, usize)>) {
+        let mut methods = HashMap::new();
+        let mut methods_by_selector = HashMap::new();
+        for (name, functions) in &abi.functions {
+            for (index, function) in functions.iter().enumerate() {
+                methods.insert(function.abi_signature(), (name.clone(), index));
+                methods_by_selector.insert(H32(function.short_signature()), (name.clone(), index));
+            }
+        }
+        (methods, methods_by_selector)
+    }
+
+    // `P`/`R` are phantom parameters of `MethodSignature` itself rather than
+    // of `lookup`, so a plain `.lookup(...)` call can't infer them from a
+    // `&str`/`String` receiver; pin them down with fully qualified syntax,
+    // the same way `Instance::method`'s own `P, R` generics do implicitly.
+    type TransferSignature = (Address, U256);
+
+    #[test]
+    fn str_signature_looks_up_by_full_signature() {
+        let abi = test_abi();
+        let (methods, methods_by_selector) = test_maps(&abi);
+        let sig = "transfer(address,uint256)";
+
+        let function = MethodSignature::<TransferSignature, bool>::lookup(
+            &sig,
+            &abi,
+            &methods,
+            &methods_by_selector,
+        )
+        .unwrap();
+
+        assert_eq!(function.name, "transfer");
+    }
+
+    #[test]
+    fn string_signature_delegates_to_str_signature() {
+        let abi = test_abi();
+        let (methods, methods_by_selector) = test_maps(&abi);
+        let sig = "transfer(address,uint256)".to_string();
+
+        let function = MethodSignature::<TransferSignature, bool>::lookup(
+            &sig,
+            &abi,
+            &methods,
+            &methods_by_selector,
+        )
+        .unwrap();
+
+        assert_eq!(function.name, "transfer");
+    }
+
+    #[test]
+    fn typed_signature_looks_up_by_selector() {
+        let abi = test_abi();
+        let (methods, methods_by_selector) = test_maps(&abi);
+        // 4-byte selector for `transfer(address,uint256)`.
+        let signature: Signature<TransferSignature, bool> =
+            Signature::new([0xa9, 0x05, 0x9c, 0xbb]);
+
+        let function = signature.lookup(&abi, &methods, &methods_by_selector).unwrap();
+
+        assert_eq!(function.name, "transfer");
+    }
+
+    #[test]
+    fn unknown_signature_errors() {
+        let abi = test_abi();
+        let (methods, methods_by_selector) = test_maps(&abi);
+        let sig = "approve(address,uint256)";
+
+        let result = MethodSignature::<TransferSignature, bool>::lookup(
+            &sig,
+            &abi,
+            &methods,
+            &methods_by_selector,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cow_signature_also_resolves_via_the_blanket_impl() {
+        let abi = test_abi();
+        let (methods, methods_by_selector) = test_maps(&abi);
+        let sig: std::borrow::Cow<str> = "transfer(address,uint256)".into();
+
+        let function = MethodSignature::<TransferSignature, bool>::lookup(
+            &sig,
+            &abi,
+            &methods,
+            &methods_by_selector,
+        )
+        .unwrap();
+
+        assert_eq!(function.name, "transfer");
+    }
+
+    #[test]
+    fn legacy_gas_price_has_no_transaction_type() {
+        assert_eq!(GasPrice::Legacy(None).transaction_type(), None);
+    }
+
+    #[test]
+    fn eip1559_gas_price_sets_transaction_type_two() {
+        let gas_price = GasPrice::Eip1559 {
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        };
+
+        assert_eq!(gas_price.transaction_type(), Some(U64::from(2)));
+    }
+}
@@ -0,0 +1,151 @@
+//! Module for resolving ENS (Ethereum Name Service) names into contract
+//! addresses, used by [`Instance::at_name`](crate::contract::Instance::at_name).
+
+use crate::errors::EnsError;
+use futures::compat::Future01CompatExt;
+use web3::api::Web3;
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, H160, H256};
+use web3::Transport;
+
+/// The canonical ENS registry address, deployed at the same address on
+/// mainnet and most public testnets.
+pub const ENS_REGISTRY: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x2e, 0x07, 0x4e, 0xc6, 0x9a, 0x0d, 0xfb, 0x29, 0x97, 0xba, 0x6c, 0x7d, 0x2e,
+    0x1e,
+]);
+
+/// Either a raw contract address or an ENS name that resolves to one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NameOrAddress {
+    /// A 20-byte contract address.
+    Address(Address),
+    /// An ENS name, resolved into an address via the ENS registry.
+    Name(String),
+}
+
+impl NameOrAddress {
+    /// Resolves this value into an address, querying the ENS registry over
+    /// `web3` if this is a [`NameOrAddress::Name`].
+    pub async fn resolve<T: Transport>(&self, web3: &Web3<T>) -> Result<Address, EnsError> {
+        match self {
+            NameOrAddress::Address(address) => Ok(*address),
+            NameOrAddress::Name(name) => resolve(web3, name).await,
+        }
+    }
+}
+
+impl From<Address> for NameOrAddress {
+    fn from(address: Address) -> Self {
+        NameOrAddress::Address(address)
+    }
+}
+
+impl From<String> for NameOrAddress {
+    fn from(name: String) -> Self {
+        NameOrAddress::Name(name)
+    }
+}
+
+impl From<&str> for NameOrAddress {
+    fn from(name: &str) -> Self {
+        NameOrAddress::Name(name.to_owned())
+    }
+}
+
+/// Resolves an ENS name into an address by looking up the canonical ENS
+/// registry's resolver for the name and then querying that resolver's
+/// address record.
+pub async fn resolve<T: Transport>(web3: &Web3<T>, name: &str) -> Result<Address, EnsError> {
+    let node = namehash(name);
+
+    let resolver = call_address(web3, ENS_REGISTRY, "resolver(bytes32)", node).await?;
+    if resolver.is_zero() {
+        return Err(EnsError::NoResolver(name.to_owned()));
+    }
+
+    let address = call_address(web3, resolver, "addr(bytes32)", node).await?;
+    if address.is_zero() {
+        return Err(EnsError::NoAddress(name.to_owned()));
+    }
+
+    Ok(address)
+}
+
+/// Computes the ENS namehash of a dot-separated name, recursively hashing
+/// from the right-most label (the TLD) down to the left-most subdomain:
+/// `namehash("") = 0x00..00` and
+/// `namehash("label.rest") = keccak256(namehash("rest") ++ keccak256("label"))`.
+pub fn namehash(name: &str) -> H256 {
+    let mut node = H256::zero();
+    if name.is_empty() {
+        return node;
+    }
+
+    for label in name.rsplit('.') {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(node.as_bytes());
+        buf[32..].copy_from_slice(&keccak256(label.as_bytes()));
+        node = H256(keccak256(&buf));
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_name_hashes_to_the_zero_node() {
+        assert_eq!(namehash(""), H256::zero());
+    }
+
+    #[test]
+    fn matches_the_known_namehash_vector_for_eth() {
+        let expected: H256 = "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+            .parse()
+            .unwrap();
+        assert_eq!(namehash("eth"), expected);
+    }
+}
+
+/// Calls a `(bytes32) -> (address)` function on a contract and decodes the
+/// result, treating a short (e.g. empty, reverted) response as the zero
+/// address.
+async fn call_address<T: Transport>(
+    web3: &Web3<T>,
+    to: Address,
+    signature: &str,
+    arg: H256,
+) -> Result<Address, EnsError> {
+    let mut data = selector(signature).to_vec();
+    data.extend_from_slice(arg.as_bytes());
+
+    let request = CallRequest {
+        from: None,
+        to: Some(to),
+        gas: None,
+        gas_price: None,
+        value: None,
+        data: Some(Bytes(data)),
+        transaction_type: None,
+        access_list: None,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+    };
+
+    let Bytes(result) = web3.eth().call(request, None).compat().await?;
+    Ok(if result.len() >= 32 {
+        Address::from_slice(&result[12..32])
+    } else {
+        Address::zero()
+    })
+}
+
+/// Computes the 4-byte function selector for a canonical Solidity function
+/// signature, e.g. `"addr(bytes32)"`.
+fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
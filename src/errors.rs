@@ -0,0 +1,192 @@
+//! Module with common error types used throughout the crate for dealing with
+//! deploying and interacting with smart contracts.
+
+use ethcontract_common::abi::Error as AbiError;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use web3::error::Error as Web3Error;
+use web3::types::H256;
+
+/// Error that can occur while locating a deployed contract.
+#[derive(Debug)]
+pub enum DeployError {
+    /// An error occurred while ABI encoding the constructor parameters.
+    Abi(AbiError),
+    /// An error occurred while performing a web3 call.
+    Web3(Web3Error),
+    /// No contract was found for the specified network ID.
+    NotFound(String),
+    /// The transaction executing the deployment reverted.
+    Reverted(Option<H256>),
+    /// The transaction executing the deployment timed out.
+    Timeout,
+    /// The transaction was dropped and never mined.
+    Dropped(H256),
+    /// A library linking error occurred while preparing the deployment.
+    Link(LinkError),
+    /// An ENS name failed to resolve to an address.
+    Ens(EnsError),
+    /// An [`ArtifactLoader`](crate::artifact::ArtifactLoader) failed to
+    /// produce an artifact.
+    Artifact(Box<dyn Error + Send + Sync>),
+}
+
+impl Display for DeployError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DeployError::Abi(err) => write!(f, "ABI error: {}", err),
+            DeployError::Web3(err) => write!(f, "web3 error: {}", err),
+            DeployError::NotFound(network_id) => {
+                write!(f, "no contract deployed on network {}", network_id)
+            }
+            DeployError::Reverted(tx) => write!(f, "deployment transaction reverted: {:?}", tx),
+            DeployError::Timeout => write!(f, "deployment transaction confirmation timed out"),
+            DeployError::Dropped(tx) => write!(f, "deployment transaction {:?} was dropped", tx),
+            DeployError::Link(err) => write!(f, "linking error: {}", err),
+            DeployError::Ens(err) => write!(f, "ENS error: {}", err),
+            DeployError::Artifact(err) => write!(f, "artifact error: {}", err),
+        }
+    }
+}
+
+impl Error for DeployError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeployError::Abi(err) => Some(err),
+            DeployError::Web3(err) => Some(err),
+            DeployError::Link(err) => Some(err),
+            DeployError::Ens(err) => Some(err),
+            DeployError::Artifact(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<Web3Error> for DeployError {
+    fn from(err: Web3Error) -> Self {
+        DeployError::Web3(err)
+    }
+}
+
+impl From<LinkError> for DeployError {
+    fn from(err: LinkError) -> Self {
+        DeployError::Link(err)
+    }
+}
+
+impl From<EnsError> for DeployError {
+    fn from(err: EnsError) -> Self {
+        DeployError::Ens(err)
+    }
+}
+
+/// Error that can occur while linking a library into a contract's bytecode.
+#[derive(Debug)]
+pub enum LinkError {
+    /// The library name is invalid, for example if it is longer than 38
+    /// characters.
+    InvalidLibraryName(String),
+    /// The bytecode does not contain an unlinked reference for the library.
+    LibraryNotFound(String),
+}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LinkError::InvalidLibraryName(name) => {
+                write!(f, "invalid library name '{}'", name)
+            }
+            LinkError::LibraryNotFound(name) => {
+                write!(f, "library '{}' not found in bytecode", name)
+            }
+        }
+    }
+}
+
+impl Error for LinkError {}
+
+/// Error that can occur while resolving an ENS name into a contract
+/// address.
+#[derive(Debug)]
+pub enum EnsError {
+    /// An error occurred while performing a web3 call.
+    Web3(Web3Error),
+    /// The ENS registry has no resolver set for the given name.
+    NoResolver(String),
+    /// The name's resolver has no address record set.
+    NoAddress(String),
+}
+
+impl Display for EnsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EnsError::Web3(err) => write!(f, "web3 error: {}", err),
+            EnsError::NoResolver(name) => write!(f, "no resolver set for ENS name '{}'", name),
+            EnsError::NoAddress(name) => {
+                write!(f, "resolver has no address record for ENS name '{}'", name)
+            }
+        }
+    }
+}
+
+impl Error for EnsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EnsError::Web3(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Web3Error> for EnsError {
+    fn from(err: Web3Error) -> Self {
+        EnsError::Web3(err)
+    }
+}
+
+/// Error that can occur while executing a contract method call or
+/// transaction.
+#[derive(Debug)]
+pub enum ExecutionError {
+    /// An error occurred while ABI encoding or decoding method parameters.
+    Abi(AbiError),
+    /// An error occurred while performing a web3 call.
+    Web3(Web3Error),
+    /// The call reverted with the specified reason, if any.
+    Revert(Option<String>),
+    /// The transaction confirmation timed out.
+    Timeout,
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ExecutionError::Abi(err) => write!(f, "ABI error: {}", err),
+            ExecutionError::Web3(err) => write!(f, "web3 error: {}", err),
+            ExecutionError::Revert(reason) => write!(f, "execution reverted: {:?}", reason),
+            ExecutionError::Timeout => write!(f, "transaction confirmation timed out"),
+        }
+    }
+}
+
+impl Error for ExecutionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExecutionError::Abi(err) => Some(err),
+            ExecutionError::Web3(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<AbiError> for ExecutionError {
+    fn from(err: AbiError) -> Self {
+        ExecutionError::Abi(err)
+    }
+}
+
+impl From<Web3Error> for ExecutionError {
+    fn from(err: Web3Error) -> Self {
+        ExecutionError::Web3(err)
+    }
+}
@@ -0,0 +1,336 @@
+//! Module for streaming raw contract event logs from a node by polling for
+//! new blocks, advancing the filter's lower bound as logs are confirmed,
+//! while detecting logs that get invalidated by a chain reorganization.
+
+use crate::errors::ExecutionError;
+use futures::compat::Future01CompatExt;
+use std::collections::HashMap;
+use std::time::Duration;
+use web3::api::Web3;
+use web3::types::{BlockNumber, FilterBuilder, Log, H256, U256, U64};
+use web3::Transport;
+
+/// The default number of blocks a log must be buried under before it is no
+/// longer tracked for a possible reorg removal.
+pub const DEFAULT_CONFIRMATIONS: u64 = 25;
+
+/// Whether a streamed item was newly observed, or is a previously observed
+/// item that a chain reorganization has invalidated.
+///
+/// A `Removed` is only ever emitted for an item that this stream previously
+/// yielded as `Added`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventStatus<T> {
+    /// A new item, observed for the first time.
+    Added(T),
+    /// A previously `Added` item that has been removed by a chain
+    /// reorganization.
+    Removed(T),
+}
+
+impl<T> EventStatus<T> {
+    /// Returns `true` if this is an `Added` status.
+    pub fn is_added(&self) -> bool {
+        matches!(self, EventStatus::Added(_))
+    }
+
+    /// Returns `true` if this is a `Removed` status.
+    pub fn is_removed(&self) -> bool {
+        matches!(self, EventStatus::Removed(_))
+    }
+
+    /// Returns a reference to the wrapped item, regardless of status.
+    pub fn inner(&self) -> &T {
+        match self {
+            EventStatus::Added(inner) | EventStatus::Removed(inner) => inner,
+        }
+    }
+
+    /// Maps the wrapped item, preserving the `Added`/`Removed` status.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> EventStatus<U> {
+        match self {
+            EventStatus::Added(inner) => EventStatus::Added(f(inner)),
+            EventStatus::Removed(inner) => EventStatus::Removed(f(inner)),
+        }
+    }
+
+    /// Maps the wrapped item through a fallible function, preserving the
+    /// `Added`/`Removed` status.
+    pub fn try_map<U, E>(self, f: impl FnOnce(T) -> Result<U, E>) -> Result<EventStatus<U>, E> {
+        Ok(match self {
+            EventStatus::Added(inner) => EventStatus::Added(f(inner)?),
+            EventStatus::Removed(inner) => EventStatus::Removed(f(inner)?),
+        })
+    }
+}
+
+/// A stream of raw logs matching a filter, produced by periodically polling
+/// a node for new logs and detecting removals caused by chain reorgs.
+///
+/// This is the lowest-level building block used by [`EventBuilder`] and
+/// [`AllEventsBuilder`](crate::contract::AllEventsBuilder) to implement
+/// strongly-typed event streams.
+#[derive(Debug)]
+pub struct LogStream<T: Transport> {
+    web3: Web3<T>,
+    filter: FilterBuilder,
+    poll_interval: Duration,
+    confirmations: u64,
+    /// Logs already yielded as `Added`, keyed by `(block_hash, log_index)`
+    /// and recording the block number they were included in, so that we can
+    /// both detect their removal and forget about them once they are
+    /// buried deep enough to be considered final.
+    seen: HashMap<(H256, U256), U64>,
+    /// The lower bound to query from on the next poll, advanced after each
+    /// successful poll to just past the confirmation window so that blocks
+    /// too deep to still be reorged aren't re-fetched forever. `None` until
+    /// the first poll, meaning the filter's own `from_block` is used as-is.
+    next_from_block: Option<U64>,
+}
+
+impl<T: Transport> LogStream<T> {
+    /// Creates a new log stream from a filter builder and a poll interval
+    /// used to throttle how often the node is queried for new logs.
+    pub fn new(web3: Web3<T>, filter: FilterBuilder, poll_interval: Duration) -> Self {
+        LogStream {
+            web3,
+            filter,
+            poll_interval,
+            confirmations: DEFAULT_CONFIRMATIONS,
+            seen: HashMap::new(),
+            next_from_block: None,
+        }
+    }
+
+    /// Sets the number of blocks a log must be buried under before this
+    /// stream stops tracking it for a possible reorg removal.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Retrieve the underlying web3 provider used by this stream.
+    pub fn web3(&self) -> Web3<T> {
+        self.web3.clone()
+    }
+
+    /// Returns the filter builder used to query logs.
+    pub fn filter(&self) -> &FilterBuilder {
+        &self.filter
+    }
+
+    /// Returns the poll interval used to throttle queries for new logs.
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Polls the node once for the current filter, returning every log the
+    /// node hasn't reported on a previous call as `Added`, and re-surfacing
+    /// any previously `Added` log that the node now reports with
+    /// `removed: true` as `Removed`.
+    ///
+    /// After the poll, the filter's lower bound is advanced to just past the
+    /// confirmation window (the same cutoff past which a log is forgotten
+    /// from `seen` below), so that a long-running stream queries a shrinking
+    /// "unconfirmed tail" of recent blocks instead of re-fetching the whole
+    /// history covered by the original filter on every call.
+    pub async fn next_batch(&mut self) -> Result<Vec<EventStatus<Log>>, ExecutionError> {
+        let filter = match self.next_from_block {
+            Some(from_block) => self
+                .filter
+                .clone()
+                .from_block(BlockNumber::Number(from_block))
+                .build(),
+            None => self.filter.clone().build(),
+        };
+        let logs = self.web3.eth().logs(filter).compat().await?;
+        let statuses = reconcile(&mut self.seen, logs);
+
+        let latest_block = self.web3.eth().block_number().compat().await?;
+        advance(&mut self.seen, &mut self.next_from_block, self.confirmations, latest_block);
+
+        Ok(statuses)
+    }
+}
+
+/// Updates `seen` with a freshly polled batch of logs, returning every log
+/// not already in `seen` as `Added` and every previously `Added` log the
+/// node now reports as `removed: true` as `Removed`. Split out of
+/// `LogStream::next_batch` so this bookkeeping can be unit tested without a
+/// node.
+fn reconcile(seen: &mut HashMap<(H256, U256), U64>, logs: Vec<Log>) -> Vec<EventStatus<Log>> {
+    let mut statuses = Vec::new();
+    for log in logs {
+        let (block_hash, log_index) = match (log.block_hash, log.log_index) {
+            (Some(block_hash), Some(log_index)) => (block_hash, log_index),
+            // Pending logs have no block yet; nothing to track.
+            _ => continue,
+        };
+
+        let key = (block_hash, log_index);
+        if log.removed.unwrap_or(false) {
+            if seen.remove(&key).is_some() {
+                statuses.push(EventStatus::Removed(log));
+            }
+        } else if !seen.contains_key(&key) {
+            seen.insert(key, log.block_number.unwrap_or_default());
+            statuses.push(EventStatus::Added(log));
+        }
+    }
+    statuses
+}
+
+/// Forgets logs buried deeper than the confirmation window and advances
+/// `next_from_block` to just past that window. Split out of
+/// `LogStream::next_batch` so this bookkeeping can be unit tested without a
+/// node.
+fn advance(
+    seen: &mut HashMap<(H256, U256), U64>,
+    next_from_block: &mut Option<U64>,
+    confirmations: u64,
+    latest_block: U64,
+) {
+    let confirmations = U64::from(confirmations);
+    seen.retain(|_, block_number| {
+        latest_block < *block_number || latest_block - *block_number < confirmations
+    });
+
+    // A log is only actually forgotten by `retain` above once the chain has
+    // reached the confirmation window (`latest_block >= confirmations`);
+    // before that, every log ever seen is still tracked (even one at block 0), so
+    // the next poll must keep starting from block 0 or a reorg of it would
+    // go unnoticed. Once a log *is* forgotten, the next poll must start one
+    // block past it -- starting at the forgotten block itself would
+    // re-query it and `reconcile` would treat it as newly added all over
+    // again.
+    let next = if latest_block >= confirmations {
+        latest_block - confirmations + U64::from(1)
+    } else {
+        U64::zero()
+    };
+    *next_from_block = Some(match *next_from_block {
+        Some(previous) => previous.max(next),
+        None => next,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::types::{Address, Bytes};
+
+    fn log(block_hash: H256, block_number: u64, log_index: u64, removed: bool) -> Log {
+        Log {
+            address: Address::zero(),
+            topics: Vec::new(),
+            data: Bytes(Vec::new()),
+            block_hash: Some(block_hash),
+            block_number: Some(U64::from(block_number)),
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: Some(U256::from(log_index)),
+            transaction_log_index: None,
+            log_type: None,
+            removed: Some(removed),
+        }
+    }
+
+    #[test]
+    fn reconcile_yields_added_for_a_new_log() {
+        let mut seen = HashMap::new();
+        let block_hash = H256::repeat_byte(0x11);
+
+        let statuses = reconcile(&mut seen, vec![log(block_hash, 1, 0, false)]);
+
+        assert_eq!(statuses, vec![EventStatus::Added(log(block_hash, 1, 0, false))]);
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn reconcile_ignores_an_already_seen_log() {
+        let mut seen = HashMap::new();
+        let block_hash = H256::repeat_byte(0x22);
+        reconcile(&mut seen, vec![log(block_hash, 1, 0, false)]);
+
+        let statuses = reconcile(&mut seen, vec![log(block_hash, 1, 0, false)]);
+
+        assert!(statuses.is_empty());
+    }
+
+    #[test]
+    fn reconcile_yields_removed_and_forgets_a_reorged_log() {
+        let mut seen = HashMap::new();
+        let block_hash = H256::repeat_byte(0x33);
+        reconcile(&mut seen, vec![log(block_hash, 1, 0, false)]);
+
+        let statuses = reconcile(&mut seen, vec![log(block_hash, 1, 0, true)]);
+
+        assert_eq!(statuses, vec![EventStatus::Removed(log(block_hash, 1, 0, true))]);
+        assert!(seen.is_empty());
+    }
+
+    #[test]
+    fn advance_forgets_logs_past_the_confirmation_window() {
+        let mut seen = HashMap::new();
+        seen.insert((H256::repeat_byte(0x44), U256::zero()), U64::from(1));
+        let mut next_from_block = None;
+
+        advance(&mut seen, &mut next_from_block, 5, U64::from(10));
+
+        assert!(seen.is_empty());
+        assert_eq!(next_from_block, Some(U64::from(6)));
+    }
+
+    #[test]
+    fn advance_keeps_logs_still_within_the_confirmation_window() {
+        let mut seen = HashMap::new();
+        seen.insert((H256::repeat_byte(0x55), U256::zero()), U64::from(8));
+        let mut next_from_block = None;
+
+        advance(&mut seen, &mut next_from_block, 5, U64::from(10));
+
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn advance_does_not_resurrect_a_log_exactly_at_the_confirmation_floor() {
+        // A log at exactly `floor` (here `latest_block - confirmations ==
+        // 5`) is forgotten by `retain`, so `next_from_block` must start
+        // *past* it or the next poll would re-query its block and
+        // `reconcile` would wrongly treat it as newly added again.
+        let mut seen = HashMap::new();
+        seen.insert((H256::repeat_byte(0x66), U256::zero()), U64::from(5));
+        let mut next_from_block = None;
+
+        advance(&mut seen, &mut next_from_block, 5, U64::from(10));
+
+        assert!(seen.is_empty());
+        assert_eq!(next_from_block, Some(U64::from(6)));
+    }
+
+    #[test]
+    fn advance_keeps_querying_from_genesis_while_still_within_the_window() {
+        // The chain hasn't gotten past the confirmation window yet, so
+        // nothing is actually forgotten by `retain` (not even a log at
+        // block 0) -- `next_from_block` must not skip ahead of a block
+        // that's still tracked, or a reorg of it would go unnoticed.
+        let mut seen = HashMap::new();
+        seen.insert((H256::repeat_byte(0x77), U256::zero()), U64::from(0));
+        let mut next_from_block = None;
+
+        advance(&mut seen, &mut next_from_block, 25, U64::from(3));
+
+        assert_eq!(seen.len(), 1);
+        assert_eq!(next_from_block, Some(U64::zero()));
+    }
+
+    #[test]
+    fn advance_never_moves_next_from_block_backwards() {
+        let mut seen = HashMap::new();
+        let mut next_from_block = Some(U64::from(20));
+
+        advance(&mut seen, &mut next_from_block, 5, U64::from(10));
+
+        assert_eq!(next_from_block, Some(U64::from(20)));
+    }
+}